@@ -0,0 +1,274 @@
+mod picker;
+
+pub use picker::{CompactionJob, CompactionPicker, SizeTieredPicker};
+
+use crate::errors::Errors;
+use crate::options::DharmaOpts;
+use crate::persistence::Persistence;
+use crate::sparse_index::TableIndex;
+use crate::storage::block::{Entry, Value};
+use crate::storage::bloom_filter::{filter_path_for, BloomFilter};
+use crate::storage::sorted_string_table_reader::SSTableReader;
+use crate::storage::sorted_string_table_writer::write_sstable;
+use crate::traits::{ResourceKey, ResourceValue};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Picks and runs size-tiered compaction jobs, keeping a `TableIndex` in
+/// sync with whatever SSTables the compaction leaves behind.
+///
+/// Compaction merges the SSTables in a `CompactionJob` with a k-way merge
+/// over their sequential `SSTableReader` cursors: a min-heap keyed on `K`
+/// holds the head record of every input table, and whenever several
+/// tables share the current minimum key only the one from the newest
+/// table (the job lists tables newest-first) is kept -- the rest are
+/// discarded, which is what lets a newer write or tombstone correctly
+/// shadow an older one.
+pub struct Compactor<K: ResourceKey> {
+    picker: SizeTieredPicker,
+    ticker: Option<Ticker>,
+    _marker: PhantomData<K>,
+}
+
+impl<K> Compactor<K>
+where
+    K: ResourceKey,
+{
+    pub fn new(options: &DharmaOpts) -> Compactor<K> {
+        let ticker = if options.background_compaction_interval_ms > 0 {
+            Some(Ticker::start(Duration::from_millis(options.background_compaction_interval_ms)))
+        } else {
+            None
+        };
+        Compactor { picker: SizeTieredPicker, ticker, _marker: PhantomData }
+    }
+
+    /// Whether the background ticker has fired since it was last checked.
+    /// A no-op (always `false`) when no background interval was
+    /// configured, leaving compaction to run only when triggered
+    /// explicitly via `Persistence::compact`.
+    pub fn is_due(&self) -> bool {
+        self.ticker.as_ref().is_some_and(Ticker::due)
+    }
+
+    /// Picks a single compaction job, if one is available, and runs it.
+    /// Returns `Ok(false)` when there was nothing to compact.
+    pub fn run<V: ResourceValue>(
+        &self,
+        options: &DharmaOpts,
+        index: &mut TableIndex<K>,
+        filters: &mut HashMap<PathBuf, BloomFilter>,
+    ) -> Result<bool, Errors> {
+        let table_paths = SSTableReader::get_valid_table_paths(&options.path)?;
+        let job = match self.picker.pick(&table_paths, options)? {
+            Some(job) => job,
+            None => return Ok(false),
+        };
+        // a job covering every live table means there is no older table
+        // left that could still need a tombstone to shadow a value for
+        // the same key -- safe to drop those tombstones for good.
+        let is_full_compaction = job.tables.len() == table_paths.len();
+        self.run_job::<V>(options, &job, is_full_compaction, index, filters)?;
+        Ok(true)
+    }
+
+    fn run_job<V: ResourceValue>(
+        &self,
+        options: &DharmaOpts,
+        job: &CompactionJob,
+        is_full_compaction: bool,
+        index: &mut TableIndex<K>,
+        filters: &mut HashMap<PathBuf, BloomFilter>,
+    ) -> Result<(), Errors> {
+        let mut readers: Vec<SSTableReader> = job
+            .tables
+            .iter()
+            .map(|path| SSTableReader::from(path, options.block_size_in_bytes, options.use_mmap))
+            .collect::<Result<_, _>>()?;
+
+        let mut heap: BinaryHeap<HeapEntry<K, V>> = BinaryHeap::new();
+        for (table_index, reader) in readers.iter().enumerate() {
+            if let Some(entry) = Self::head_entry(reader, table_index) {
+                heap.push(entry);
+            }
+        }
+
+        let mut merged: Vec<(K, Entry<V>)> = Vec::new();
+        while let Some(min_key) = heap.peek().map(|entry| entry.key.clone()) {
+            let mut winner: Option<HeapEntry<K, V>> = None;
+            while let Some(next) = heap.peek() {
+                if next.key != min_key {
+                    break;
+                }
+                let entry = heap.pop().unwrap();
+                let table_index = entry.table_index;
+                if winner.as_ref().is_none_or(|w| entry.table_index < w.table_index) {
+                    winner = Some(entry);
+                }
+                readers[table_index].next();
+                if let Some(refill) = Self::head_entry(&readers[table_index], table_index) {
+                    heap.push(refill);
+                }
+            }
+            let winner = winner.expect("loop only entered while the heap held a matching entry");
+            if is_full_compaction && matches!(winner.entry, Entry::Tombstone) {
+                continue;
+            }
+            merged.push((winner.key, winner.entry));
+        }
+
+        // `job.tables` is newest-first (see `SizeTieredPicker::pick`), so
+        // its first entry is the newest input. The merged table takes
+        // over that id rather than the directory's global next id --
+        // the size-tiered picker groups tables by byte size, not
+        // recency, so a job is frequently not the newest tables on
+        // disk, and a global next id would leapfrog an untouched,
+        // newer table outside the job, making the merge incorrectly
+        // shadow it on the next read.
+        let merged_id = SSTableReader::table_id(&job.tables[0])?;
+
+        for path in &job.tables {
+            std::fs::remove_file(path).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+            // best-effort: a missing/unreadable sidecar here just means
+            // `get` never got to skip this table via its filter, same as
+            // at load time in `create`.
+            let _ = std::fs::remove_file(filter_path_for(path));
+            filters.remove(path);
+        }
+
+        let (merged_path, merged_filter) = write_sstable(options, &merged, merged_id)?;
+
+        index.remove_tables(&job.tables);
+        let table_index = Persistence::<K>::populate_index_from_path::<V>(options, &merged_path)
+            .map_err(|_| Errors::DB_INDEX_UPDATE_FAILED)?;
+        index.set_table(merged_path.clone(), table_index);
+        filters.insert(merged_path, merged_filter);
+        Ok(())
+    }
+
+    fn head_entry<V: ResourceValue>(
+        reader: &SSTableReader,
+        table_index: usize,
+    ) -> Option<HeapEntry<K, V>> {
+        if !reader.has_next() {
+            return None;
+        }
+        let sstable_value = reader.read();
+        let record: Value<K, V> = bincode::deserialize(sstable_value.data).ok()?;
+        Some(HeapEntry { key: record.key, table_index, entry: record.entry })
+    }
+}
+
+struct HeapEntry<K: ResourceKey, V: ResourceValue> {
+    key: K,
+    table_index: usize,
+    entry: Entry<V>,
+}
+
+impl<K: ResourceKey, V: ResourceValue> PartialEq for HeapEntry<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: ResourceKey, V: ResourceValue> Eq for HeapEntry<K, V> {}
+
+impl<K: ResourceKey, V: ResourceValue> PartialOrd for HeapEntry<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: ResourceKey, V: ResourceValue> Ord for HeapEntry<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse the key comparison so the
+        // smallest key surfaces first.
+        other.key.cmp(&self.key)
+    }
+}
+
+/// Background thread that periodically signals "check for compaction
+/// work". It never touches the filesystem itself -- actual merging
+/// happens on whichever thread calls `Compactor::run`, so the `K`/`V`
+/// generics never have to cross the thread boundary.
+struct Ticker {
+    handle: Option<thread::JoinHandle<()>>,
+    due: Receiver<()>,
+    stop: Sender<()>,
+}
+
+impl Ticker {
+    fn start(interval: Duration) -> Ticker {
+        let (due_tx, due_rx) = mpsc::channel();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let handle = thread::spawn(move || loop {
+            match stop_rx.recv_timeout(interval) {
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    if due_tx.send(()).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ticker { handle: Some(handle), due: due_rx, stop: stop_tx }
+    }
+
+    fn due(&self) -> bool {
+        match self.due.try_recv() {
+            Ok(()) => true,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => false,
+        }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::DharmaOpts;
+    use crate::persistence::Persistence;
+    use crate::storage::block::Entry;
+    use crate::test_util::temp_dir;
+
+    /// A compaction job is a size tier, not a recency suffix: a newer
+    /// table can sit outside the job just because it happens to be a
+    /// different size. The merged table must not be given an id newer
+    /// than such a table, or a stale value the merge correctly dropped
+    /// would shadow the newer table's value on the next read.
+    #[test]
+    fn compaction_does_not_shadow_a_newer_table_outside_the_job() {
+        let options = DharmaOpts::default(temp_dir("compaction_no_leapfrog"));
+        let (mut db, _recovered) = Persistence::<String>::create::<String>(options).unwrap();
+
+        // four small, similarly-sized tables sharing key "x" -- enough
+        // to form a tier and trigger compaction (min_tables_to_compact
+        // defaults to 4).
+        for i in 0..4 {
+            db.flush::<String>(&[("x".to_string(), Entry::Value(format!("stale-{i}")))]).unwrap();
+        }
+        // a much larger table -- its own size tier, so the picker won't
+        // select it alongside the four small ones -- written after them,
+        // so it holds the newest value for "x".
+        let mut padded: Vec<(String, Entry<String>)> = (0..200)
+            .map(|i| (format!("padding-{i:04}"), Entry::Value("padding".to_string())))
+            .collect();
+        padded.push(("x".to_string(), Entry::Value("newest".to_string())));
+        db.flush::<String>(&padded).unwrap();
+
+        assert!(db.compact::<String>().unwrap(), "expected the small tier to be compacted");
+        assert_eq!(db.get::<String>(&"x".to_string()).unwrap(), Some("newest".to_string()));
+    }
+}
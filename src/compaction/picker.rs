@@ -0,0 +1,79 @@
+use crate::errors::Errors;
+use crate::options::DharmaOpts;
+use std::fs;
+use std::path::PathBuf;
+
+/// A set of SSTables chosen for compaction, ordered newest-first so the
+/// merge step can use position as the tie-break when two tables hold the
+/// same key.
+#[derive(Debug, Clone)]
+pub struct CompactionJob {
+    pub tables: Vec<PathBuf>,
+}
+
+/// Strategy for deciding which SSTables (if any) should be merged next.
+pub trait CompactionPicker {
+    fn pick(
+        &self,
+        table_paths: &[PathBuf],
+        options: &DharmaOpts,
+    ) -> Result<Option<CompactionJob>, Errors>;
+}
+
+/// Groups tables into size tiers (buckets of similarly-sized files) and
+/// picks the first tier that has accumulated at least
+/// `options.min_tables_to_compact` tables.
+pub struct SizeTieredPicker;
+
+impl SizeTieredPicker {
+    /// Tables within this multiple of the smallest table in a tier are
+    /// considered part of that tier.
+    const TIER_SIZE_RATIO: f64 = 2.0;
+
+    fn tiers(table_paths: &[PathBuf]) -> Result<Vec<Vec<PathBuf>>, Errors> {
+        let mut sized: Vec<(PathBuf, u64)> = table_paths
+            .iter()
+            .map(|path| {
+                fs::metadata(path)
+                    .map(|meta| (path.clone(), meta.len()))
+                    .map_err(|_| Errors::SSTABLE_READ_FAILED)
+            })
+            .collect::<Result<_, _>>()?;
+        sized.sort_by_key(|(_, size)| *size);
+
+        let mut tiers: Vec<Vec<(PathBuf, u64)>> = Vec::new();
+        for entry in sized {
+            match tiers.last_mut() {
+                Some(tier) if (entry.1 as f64) <= tier[0].1 as f64 * Self::TIER_SIZE_RATIO => {
+                    tier.push(entry);
+                }
+                _ => tiers.push(vec![entry]),
+            }
+        }
+
+        Ok(tiers
+            .into_iter()
+            .map(|tier| tier.into_iter().map(|(path, _)| path).collect())
+            .collect())
+    }
+}
+
+impl CompactionPicker for SizeTieredPicker {
+    fn pick(
+        &self,
+        table_paths: &[PathBuf],
+        options: &DharmaOpts,
+    ) -> Result<Option<CompactionJob>, Errors> {
+        for tier in Self::tiers(table_paths)? {
+            if tier.len() >= options.min_tables_to_compact {
+                // `write_sstable` names tables with a monotonically
+                // increasing index, so sorting descending puts the most
+                // recently written table first.
+                let mut tables = tier;
+                tables.sort_by(|a, b| b.cmp(a));
+                return Ok(Some(CompactionJob { tables }));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,17 @@
+/// All errors surfaced by the persistence layer.
+///
+/// Variants are intentionally coarse-grained: callers are expected to
+/// react to the *kind* of failure (write vs. read vs. index) rather than
+/// branch on the underlying cause.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum Errors {
+    DB_WRITE_FAILED,
+    DB_INDEX_INITIALIZATION_FAILED,
+    DB_INDEX_UPDATE_FAILED,
+    SSTABLE_CREATION_FAILED,
+    SSTABLE_READ_FAILED,
+    UNSUPPORTED_SSTABLE_VERSION,
+    WAL_INITIALIZATION_FAILED,
+    WAL_WRITE_FAILED,
+}
@@ -0,0 +1,11 @@
+pub mod compaction;
+pub mod errors;
+pub mod options;
+pub mod persistence;
+pub mod scan;
+pub mod sparse_index;
+pub mod storage;
+pub mod traits;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+/// Configuration for a `Persistence` instance.
+#[derive(Clone, Debug)]
+pub struct DharmaOpts {
+    /// Directory the database keeps its SSTables and WAL in.
+    pub path: PathBuf,
+    /// Size of a single read/write block within an SSTable.
+    pub block_size_in_bytes: usize,
+    /// Every `sparse_index_sampling_rate`-th record in an SSTable gets an
+    /// entry in the in-memory sparse index.
+    pub sparse_index_sampling_rate: usize,
+    /// Minimum number of similarly-sized SSTables a size tier must
+    /// accumulate before the compactor picks it for merging.
+    pub min_tables_to_compact: usize,
+    /// How often the background compaction ticker checks for work, in
+    /// milliseconds. `0` disables the background thread entirely, leaving
+    /// compaction to be triggered explicitly via `Persistence::compact`.
+    pub background_compaction_interval_ms: u64,
+    /// When `true`, `SSTableReader` serves reads from a memory-mapped
+    /// view of each SSTable instead of issuing a syscall per read.
+    /// Falls back to the buffered reader transparently if the mapping
+    /// fails.
+    pub use_mmap: bool,
+    /// Target false-positive rate for each SSTable's bloom filter; lower
+    /// values trade more bits per key for fewer wasted table reads.
+    pub bloom_filter_false_positive_rate: f64,
+}
+
+impl DharmaOpts {
+    pub fn default(path: PathBuf) -> Self {
+        DharmaOpts {
+            path,
+            block_size_in_bytes: 4096,
+            sparse_index_sampling_rate: 16,
+            min_tables_to_compact: 4,
+            background_compaction_interval_ms: 0,
+            use_mmap: false,
+            bloom_filter_false_positive_rate: 0.01,
+        }
+    }
+}
@@ -1,71 +1,133 @@
+use crate::compaction::Compactor;
 use crate::errors::Errors;
 use crate::options::DharmaOpts;
-use crate::sparse_index::{SparseIndex, TableAddress};
-use crate::storage::block::Value;
+use crate::scan::Scan;
+use crate::sparse_index::{SparseIndex, TableIndex};
+use crate::storage::block::{Entry, Value};
+use crate::storage::bloom_filter::{filter_path_for, BloomFilter};
+use crate::storage::format;
 use crate::storage::sorted_string_table_reader::{SSTableReader, SSTableValue};
-use crate::storage::sorted_string_table_writer::write_sstable;
+use crate::storage::sorted_string_table_writer::{rewrite_with_header, write_sstable};
 use crate::traits::{ResourceKey, ResourceValue};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
 use std::cmp::Ordering;
 use crate::storage::write_ahead_log::WriteAheadLog;
 
+/// Entries recovered from the write-ahead log by `Persistence::create`,
+/// pulled out as its own alias purely to keep that signature readable.
+pub type RecoveredEntries<K, V> = Vec<(K, Entry<V>)>;
+
 /// Encapsulates all functionality that involves reading
 /// and writing to File System.
 pub struct Persistence<K: ResourceKey> {
     options: DharmaOpts,
-    index: SparseIndex<K>,
+    index: TableIndex<K>,
     log: WriteAheadLog,
+    compactor: Compactor<K>,
+    filters: HashMap<PathBuf, BloomFilter>,
 }
 
 impl<K> Persistence<K>
 where
     K: ResourceKey,
 {
-    pub fn create<V: ResourceValue>(options: DharmaOpts) -> Result<Persistence<K>, Errors> {
+    /// Opens (or creates) the database at `options.path`. Besides the
+    /// `Persistence` handle, returns every `(K, Entry<V>)` recovered from
+    /// the write-ahead log -- inserts and deletes that were durably
+    /// logged but never made it into an SSTable before the process last
+    /// stopped. The caller is expected to seed its memtable with these
+    /// before serving reads, so nothing logged-but-unflushed is lost.
+    pub fn create<V: ResourceValue>(
+        options: DharmaOpts,
+    ) -> Result<(Persistence<K>, RecoveredEntries<K, V>), Errors> {
         // try to create write ahead log
-        let log_result = WriteAheadLog::new(options.clone());
-        if log_result.is_ok() {
-            // read all SSTables and create the sparse index
-            let sstable_paths = SSTableReader::get_valid_table_paths(&options.path)?;
-            // read through each SSTable and create the sparse index on startup
-            let mut index = SparseIndex::new();
-            for path in sstable_paths {
-                let load_result =
-                    Persistence::populate_index_from_path::<V>(&options, &path, &mut index);
-                if load_result.is_err() {
-                    return Err(Errors::DB_INDEX_INITIALIZATION_FAILED);
-                }
+        let log = WriteAheadLog::new(options.clone())?;
+        let recovered = log
+            .recover::<K, V>()?
+            .into_iter()
+            .map(|record| (record.key, record.entry))
+            .collect();
+
+        // read all SSTables and create the sparse index
+        let sstable_paths = SSTableReader::get_valid_table_paths(&options.path)?;
+        // carry any table written before the versioned header (or by
+        // an older, still-upgradeable version) forward before it is
+        // ever opened with `SSTableReader::from`
+        for path in &sstable_paths {
+            Persistence::<K>::upgrade::<V>(&options, path)?;
+        }
+        // read through each SSTable and create the sparse index on startup
+        let mut index = TableIndex::new();
+        let mut filters = HashMap::new();
+        for path in sstable_paths {
+            let load_result = Persistence::populate_index_from_path::<V>(&options, &path);
+            match load_result {
+                Ok(table_index) => index.set_table(path.clone(), table_index),
+                Err(_) => return Err(Errors::DB_INDEX_INITIALIZATION_FAILED),
+            }
+            // a missing/unreadable filter just means this table never
+            // gets skipped by the bloom-filter check in `get` -- not
+            // fatal, since the SSTable itself is still authoritative.
+            if let Ok(filter) = BloomFilter::load(&filter_path_for(&path)) {
+                filters.insert(path, filter);
             }
-            Ok(Persistence { log: log_result.unwrap(), options, index })
         }
-        Err(log_result.err().unwrap())
+        let compactor = Compactor::new(&options);
+        Ok((Persistence { log, options, index, compactor, filters }, recovered))
+    }
+
+    /// Runs a single compaction pass, if the size-tiered picker finds a
+    /// tier with enough tables accumulated. Returns `Ok(false)` when there
+    /// was nothing to compact. Safe to call at any time; `flush` already
+    /// calls this whenever the background ticker is due.
+    pub fn compact<V: ResourceValue>(&mut self) -> Result<bool, Errors> {
+        self.compactor.run::<V>(&self.options, &mut self.index, &mut self.filters)
     }
 
     pub fn get<V: ResourceValue>(&mut self, key: &K) -> Result<Option<V>, Errors> {
-        // read SSTables and return the value is present
-        let maybe_address = self.index.get_nearest_address(key);
-        if maybe_address.is_some() {
-            let address = maybe_address.unwrap();
-            let mut reader = SSTableReader::from(&address.path, self.options.block_size_in_bytes)?;
-            // try to find the value in the sstable
-            let seek_result = reader.seek_closest(address.offset);
-            // if seek offset is invalid then return errror
-            // this should never happen as long as SSTables and Sparse Index are in sync
-            if seek_result.is_ok() {
-                while reader.has_next() {
-                    let sstable_value = reader.read();
-                    let record =
-                        bincode::deserialize::<Value<K, V>>(&sstable_value.data).unwrap();
-                    match record.key.cmp(key) {
-                        Ordering::Less => {
-                            reader.next();
-                        }
-                        Ordering::Equal => {
-                            return Ok(Some(record.value));
-                        }
-                        Ordering::Greater => {
-                            return Ok(None);
-                        }
+        // search every SSTable newest-to-oldest so a tombstone or value in
+        // a more recent table correctly shadows an older one.
+        for (path, table_index) in self.index.newest_to_oldest() {
+            if let Some(filter) = self.filters.get(path) {
+                if !filter.may_contain(key) {
+                    continue;
+                }
+            }
+            let maybe_offset = table_index.get_nearest_offset(key);
+            let offset = match maybe_offset {
+                Some(offset) => offset,
+                // no sampled key in this table is <= `key`, so the key,
+                // if it existed here, would sort before the table's first
+                // (always-sampled) record -- it can't be in this table.
+                None => continue,
+            };
+            let mut reader =
+                SSTableReader::from(path, self.options.block_size_in_bytes, self.options.use_mmap)?;
+            if reader.seek_closest(offset).is_err() {
+                // this should never happen as long as SSTables and the
+                // sparse index are in sync
+                continue;
+            }
+            while reader.has_next() {
+                let sstable_value = reader.read();
+                let record =
+                    bincode::deserialize::<Value<K, V>>(sstable_value.data).unwrap();
+                match record.key.cmp(key) {
+                    Ordering::Less => {
+                        reader.next();
+                    }
+                    Ordering::Equal => {
+                        return Ok(match record.entry {
+                            Entry::Value(v) => Some(v),
+                            Entry::Tombstone => None,
+                        });
+                    }
+                    Ordering::Greater => {
+                        // key is absent from this table; fall through to
+                        // the next older one.
+                        break;
                     }
                 }
             }
@@ -73,63 +135,137 @@ where
         Ok(None)
     }
 
+    /// Returns a lazy iterator yielding every `(K, V)` with a key in
+    /// `start..end`, in ascending order, as of the SSTables currently on
+    /// disk -- inserts and deletes still sitting in the WAL/memtable are
+    /// not reflected. See `Scan` for how the merge across tables works.
+    pub fn scan<V: ResourceValue>(
+        &self,
+        start: Bound<K>,
+        end: Bound<K>,
+    ) -> Result<Scan<K, V>, Errors> {
+        Scan::new(&self.options, &self.index, start, end)
+    }
+
     pub fn insert<V: ResourceValue>(&mut self, key: K, value: V) -> Result<(), Errors> {
-        let log_write_result = self.log.append(key.clone(), value.clone());
+        let log_write_result = self.log.append(Value::of(key, value));
         if log_write_result.is_ok() {
             return Ok(());
         }
         Err(Errors::DB_WRITE_FAILED)
     }
 
-    pub fn flush<V: ResourceValue>(&mut self, values: &Vec<(K, V)>) -> Result<(), Errors> {
-        // get the existing SSTable paths
-        let paths = SSTableReader::get_valid_table_paths(&self.options.path)?;
-        let flush_result = write_sstable(&self.options, values, paths.len());
-        if flush_result.is_ok() {
-            let new_sstable_path = flush_result.unwrap();
-            //TODO: clear WAL log here
-            let index_update_result = Persistence::populate_index_from_path::<V>(
-                &self.options,
-                &new_sstable_path,
-                &mut self.index,
-            );
-            if index_update_result.is_err() {
-                return Err(Errors::DB_INDEX_UPDATE_FAILED);
+    pub fn delete<V: ResourceValue>(&mut self, key: &K) -> Result<(), Errors> {
+        // add a tombstone marker to the Write Ahead Log; it shadows older
+        // values for `key` once flushed, and is only dropped for good
+        // once compaction determines no older table can still hold it.
+        let log_write_result = self.log.append(Value::<K, V>::tombstone(key.clone()));
+        if log_write_result.is_ok() {
+            return Ok(());
+        }
+        Err(Errors::DB_WRITE_FAILED)
+    }
+
+    pub fn flush<V: ResourceValue>(&mut self, entries: &[(K, Entry<V>)]) -> Result<(), Errors> {
+        // an id one past the largest currently on disk -- never a count
+        // of tables, which can collide with a table compaction has left
+        // outside of what's being counted.
+        let next_id = SSTableReader::next_table_id(&self.options.path)?;
+        let flush_result = write_sstable(&self.options, entries, next_id);
+        if let Ok((new_sstable_path, filter)) = flush_result {
+            // the new SSTable is durably written, so everything the WAL
+            // held is now safe to drop.
+            self.log.clear()?;
+            let index_update_result =
+                Persistence::populate_index_from_path::<V>(&self.options, &new_sstable_path);
+            match index_update_result {
+                Ok(table_index) => self.index.set_table(new_sstable_path.clone(), table_index),
+                Err(_) => return Err(Errors::DB_INDEX_UPDATE_FAILED),
+            }
+            self.filters.insert(new_sstable_path, filter);
+            if self.compactor.is_due() {
+                self.compact::<V>()?;
             }
             return Ok(());
         }
         Err(Errors::SSTABLE_CREATION_FAILED)
     }
 
-    pub fn delete(&mut self, key: &K) -> Result<(), Errors> {
-        // add delete marker to Write Ahead Log
-        unimplemented!()
+    /// Brings `path` up to `format::CURRENT_VERSION` if it isn't already
+    /// there -- either because it predates the versioned header entirely
+    /// or because it was written by an older, still-upgradeable version.
+    /// A table already on the current version is left untouched. A table
+    /// from a newer version than this build understands is rejected
+    /// rather than silently misread.
+    fn upgrade<V: ResourceValue>(options: &DharmaOpts, path: &Path) -> Result<(), Errors> {
+        let version = SSTableReader::table_version(path).unwrap_or_default();
+        if version == format::CURRENT_VERSION {
+            return Ok(());
+        }
+        if version > format::CURRENT_VERSION {
+            return Err(Errors::UNSUPPORTED_SSTABLE_VERSION);
+        }
+        let mut reader =
+            SSTableReader::from_legacy(path, options.block_size_in_bytes, options.use_mmap)?;
+        let mut entries: Vec<(K, Entry<V>)> = Vec::new();
+        while reader.has_next() {
+            let sstable_value: SSTableValue = reader.read();
+            let record: Value<K, V> = bincode::deserialize(sstable_value.data)
+                .map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+            entries.push((record.key, record.entry));
+            reader.next();
+        }
+        rewrite_with_header(path, &entries)
     }
 
-    fn populate_index_from_path<V: ResourceValue>(
+    pub(crate) fn populate_index_from_path<V: ResourceValue>(
         options: &DharmaOpts,
-        path: &PathBuf,
-        index: &mut SparseIndex<K>,
-    ) -> Result<(), Errors> {
+        path: &Path,
+    ) -> Result<SparseIndex<K>, Errors> {
         let mut counter = 0;
-        let maybe_reader = SSTableReader::from(path, options.block_size_in_bytes);
-        if maybe_reader.is_ok() {
-            let mut reader = maybe_reader.unwrap();
+        let maybe_reader = SSTableReader::from(path, options.block_size_in_bytes, options.use_mmap);
+        if let Ok(mut reader) = maybe_reader {
+            let mut table_index = SparseIndex::new();
             while reader.has_next() {
                 if counter % options.sparse_index_sampling_rate == 0 {
                     let sstable_value: SSTableValue = reader.read();
                     let record: Value<K, V> =
-                        bincode::deserialize(sstable_value.data.as_slice()).unwrap();
-                    let key = record.key;
-                    let offset = sstable_value.offset;
-                    let address = TableAddress::new(path, offset);
-                    index.update(key.clone(), address);
+                        bincode::deserialize(sstable_value.data).unwrap();
+                    table_index.update(record.key, sstable_value.offset);
                 }
                 counter += 1;
                 reader.next();
             }
-            return Ok(());
+            return Ok(table_index);
         }
         Err(Errors::SSTABLE_READ_FAILED)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn newer_table_tombstone_shadows_older_table_value() {
+        let options = DharmaOpts::default(temp_dir("tombstone_shadow"));
+        let (mut db, _recovered) = Persistence::<String>::create::<String>(options).unwrap();
+
+        db.flush::<String>(&[("a".to_string(), Entry::Value("first".to_string()))]).unwrap();
+        db.flush::<String>(&[("a".to_string(), Entry::Tombstone)]).unwrap();
+
+        assert_eq!(db.get::<String>(&"a".to_string()).unwrap(), None);
+    }
+
+    #[test]
+    fn newer_table_value_shadows_older_table_value() {
+        let options = DharmaOpts::default(temp_dir("value_shadow"));
+        let (mut db, _recovered) = Persistence::<String>::create::<String>(options).unwrap();
+
+        db.flush::<String>(&[("a".to_string(), Entry::Value("first".to_string()))]).unwrap();
+        db.flush::<String>(&[("a".to_string(), Entry::Value("second".to_string()))]).unwrap();
+
+        assert_eq!(db.get::<String>(&"a".to_string()).unwrap(), Some("second".to_string()));
+    }
+}
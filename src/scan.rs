@@ -0,0 +1,210 @@
+use crate::errors::Errors;
+use crate::options::DharmaOpts;
+use crate::sparse_index::{SparseIndex, TableIndex};
+use crate::storage::block::{Entry, Value};
+use crate::storage::sorted_string_table_reader::SSTableReader;
+use crate::traits::{ResourceKey, ResourceValue};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ops::Bound;
+
+/// Lazy ascending-key iterator over every live SSTable, returned by
+/// `Persistence::scan`.
+///
+/// Built the same way `Compactor` merges tables for a compaction job: a
+/// min-heap of per-table head records keyed on `K`, with a sequential
+/// `SSTableReader` cursor behind each heap entry. Whenever several
+/// tables share the current minimum key, only the one from the newest
+/// table is kept so a more recent value or tombstone correctly shadows
+/// an older one; a shadowing tombstone is suppressed rather than
+/// yielded. Each table's cursor is seeked to the sparse-index offset
+/// nearest the lower bound up front, so a narrow range scan does not
+/// have to read the table from its first record.
+pub struct Scan<K: ResourceKey, V: ResourceValue> {
+    readers: Vec<SSTableReader>,
+    heap: BinaryHeap<HeapEntry<K, V>>,
+    end: Bound<K>,
+}
+
+impl<K, V> Scan<K, V>
+where
+    K: ResourceKey,
+    V: ResourceValue,
+{
+    pub(crate) fn new(
+        options: &DharmaOpts,
+        index: &TableIndex<K>,
+        start: Bound<K>,
+        end: Bound<K>,
+    ) -> Result<Scan<K, V>, Errors> {
+        // newest-first, matching the tie-break `HeapEntry::table_index`
+        // relies on below.
+        let mut readers = Vec::new();
+        for (path, table_index) in index.newest_to_oldest() {
+            let mut reader =
+                SSTableReader::from(path, options.block_size_in_bytes, options.use_mmap)?;
+            if let Some(offset) = Self::seek_offset(table_index, &start) {
+                reader.seek_closest(offset)?;
+            }
+            Self::skip_below_lower_bound(&mut reader, &start)?;
+            readers.push(reader);
+        }
+
+        let mut heap = BinaryHeap::new();
+        for (table_index, reader) in readers.iter().enumerate() {
+            if let Some(entry) = Self::head_entry(reader, table_index, &end) {
+                heap.push(entry);
+            }
+        }
+        Ok(Scan { readers, heap, end })
+    }
+
+    fn seek_offset(table_index: &SparseIndex<K>, start: &Bound<K>) -> Option<usize> {
+        match start {
+            Bound::Unbounded => None,
+            Bound::Included(key) | Bound::Excluded(key) => table_index.get_nearest_offset(key),
+        }
+    }
+
+    /// The sparse index only gets the cursor to the largest *sampled*
+    /// key at or before `start` -- walk forward those last few records
+    /// until the lower bound is actually satisfied.
+    fn skip_below_lower_bound(reader: &mut SSTableReader, start: &Bound<K>) -> Result<(), Errors> {
+        while reader.has_next() {
+            let sstable_value = reader.read();
+            let record: Value<K, V> = bincode::deserialize(sstable_value.data)
+                .map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+            if Self::satisfies_lower(&record.key, start) {
+                break;
+            }
+            reader.next();
+        }
+        Ok(())
+    }
+
+    fn satisfies_lower(key: &K, start: &Bound<K>) -> bool {
+        match start {
+            Bound::Unbounded => true,
+            Bound::Included(bound) => key >= bound,
+            Bound::Excluded(bound) => key > bound,
+        }
+    }
+
+    fn satisfies_upper(key: &K, end: &Bound<K>) -> bool {
+        match end {
+            Bound::Unbounded => true,
+            Bound::Included(bound) => key <= bound,
+            Bound::Excluded(bound) => key < bound,
+        }
+    }
+
+    /// The table's current record, unless it is missing or has passed
+    /// `end` -- in which case this table contributes nothing further to
+    /// the scan and is simply left out of the heap for good.
+    fn head_entry(
+        reader: &SSTableReader,
+        table_index: usize,
+        end: &Bound<K>,
+    ) -> Option<HeapEntry<K, V>> {
+        if !reader.has_next() {
+            return None;
+        }
+        let sstable_value = reader.read();
+        let record: Value<K, V> = bincode::deserialize(sstable_value.data).ok()?;
+        if !Self::satisfies_upper(&record.key, end) {
+            return None;
+        }
+        Some(HeapEntry { key: record.key, table_index, entry: record.entry })
+    }
+}
+
+impl<K, V> Iterator for Scan<K, V>
+where
+    K: ResourceKey,
+    V: ResourceValue,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let min_key = self.heap.peek()?.key.clone();
+            let mut winner: Option<HeapEntry<K, V>> = None;
+            while let Some(next) = self.heap.peek() {
+                if next.key != min_key {
+                    break;
+                }
+                let entry = self.heap.pop().unwrap();
+                let table_index = entry.table_index;
+                if winner.as_ref().is_none_or(|w| entry.table_index < w.table_index) {
+                    winner = Some(entry);
+                }
+                self.readers[table_index].next();
+                if let Some(refill) = Self::head_entry(&self.readers[table_index], table_index, &self.end) {
+                    self.heap.push(refill);
+                }
+            }
+            let winner = winner.expect("loop only entered while the heap held a matching entry");
+            match winner.entry {
+                // a more recent tombstone for this key shadows every
+                // older table's value or tombstone for it; nothing to
+                // yield, move on to the next key.
+                Entry::Tombstone => continue,
+                Entry::Value(value) => return Some((winner.key, value)),
+            }
+        }
+    }
+}
+
+struct HeapEntry<K: ResourceKey, V: ResourceValue> {
+    key: K,
+    table_index: usize,
+    entry: Entry<V>,
+}
+
+impl<K: ResourceKey, V: ResourceValue> PartialEq for HeapEntry<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: ResourceKey, V: ResourceValue> Eq for HeapEntry<K, V> {}
+
+impl<K: ResourceKey, V: ResourceValue> PartialOrd for HeapEntry<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: ResourceKey, V: ResourceValue> Ord for HeapEntry<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse the key comparison so the
+        // smallest key surfaces first.
+        other.key.cmp(&self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::DharmaOpts;
+    use crate::persistence::Persistence;
+    use crate::storage::block::Entry;
+    use crate::test_util::temp_dir;
+    use std::ops::Bound;
+
+    #[test]
+    fn scan_respects_inclusive_and_exclusive_bounds() {
+        let options = DharmaOpts::default(temp_dir("scan_bounds"));
+        let (mut db, _recovered) = Persistence::<i32>::create::<i32>(options).unwrap();
+        let entries: Vec<(i32, Entry<i32>)> =
+            (0..5).map(|i| (i, Entry::Value(i * 10))).collect();
+        db.flush::<i32>(&entries).unwrap();
+
+        let inclusive: Vec<(i32, i32)> =
+            db.scan::<i32>(Bound::Included(1), Bound::Included(3)).unwrap().collect();
+        assert_eq!(inclusive, vec![(1, 10), (2, 20), (3, 30)]);
+
+        let exclusive: Vec<(i32, i32)> =
+            db.scan::<i32>(Bound::Excluded(1), Bound::Excluded(3)).unwrap().collect();
+        assert_eq!(exclusive, vec![(2, 20)]);
+    }
+}
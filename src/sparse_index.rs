@@ -0,0 +1,87 @@
+use crate::traits::ResourceKey;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Sparse index over a single SSTable: maps a sampled key to the byte
+/// offset within that table where it can be found. Because only every
+/// `sparse_index_sampling_rate`-th record is sampled, a lookup resolves
+/// to the nearest offset at or before the requested key and the caller
+/// scans forward from there.
+pub struct SparseIndex<K: ResourceKey> {
+    entries: BTreeMap<K, usize>,
+}
+
+impl<K> Default for SparseIndex<K>
+where
+    K: ResourceKey,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> SparseIndex<K>
+where
+    K: ResourceKey,
+{
+    pub fn new() -> SparseIndex<K> {
+        SparseIndex { entries: BTreeMap::new() }
+    }
+
+    pub fn update(&mut self, key: K, offset: usize) {
+        self.entries.insert(key, offset);
+    }
+
+    /// Returns the offset of the largest indexed key <= `key`.
+    pub fn get_nearest_offset(&self, key: &K) -> Option<usize> {
+        self.entries.range(..=key.clone()).next_back().map(|(_, offset)| *offset)
+    }
+}
+
+/// Sparse indexes for every live SSTable, kept in the same
+/// ascending-by-path order as `SSTableReader::get_valid_table_paths` --
+/// which, since table file names are zero-padded ids assigned in
+/// increasing creation order (see `TABLE_ID_WIDTH`), also means
+/// oldest-to-newest.
+pub struct TableIndex<K: ResourceKey> {
+    tables: Vec<(PathBuf, SparseIndex<K>)>,
+}
+
+impl<K> Default for TableIndex<K>
+where
+    K: ResourceKey,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> TableIndex<K>
+where
+    K: ResourceKey,
+{
+    pub fn new() -> TableIndex<K> {
+        TableIndex { tables: Vec::new() }
+    }
+
+    /// Registers (or replaces) the sparse index for `path`.
+    pub fn set_table(&mut self, path: PathBuf, index: SparseIndex<K>) {
+        self.tables.retain(|(existing, _)| existing != &path);
+        self.tables.push((path, index));
+        self.tables.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+
+    /// Drops the indexes for every one of `paths`. Used after compaction
+    /// replaces a set of SSTables with a single merged one, so stale
+    /// entries pointing at the now-deleted inputs don't linger.
+    pub fn remove_tables(&mut self, paths: &[PathBuf]) {
+        self.tables.retain(|(path, _)| !paths.contains(path));
+    }
+
+    /// Iterates tables newest-first -- the order `get` must search so a
+    /// tombstone or value in a more recent table correctly shadows an
+    /// older one.
+    pub fn newest_to_oldest(&self) -> impl Iterator<Item = &(PathBuf, SparseIndex<K>)> {
+        self.tables.iter().rev()
+    }
+}
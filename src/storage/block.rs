@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// What a key maps to in the log/SSTable record format: either a live
+/// value, or a tombstone recording that the key was deleted. Tombstones
+/// are what let `delete` work in an append-only, immutable-SSTable
+/// design -- the delete is just another record that sorts and merges
+/// like any other, and shadows older values for the same key until
+/// compaction is able to drop it for good.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Entry<V> {
+    Value(V),
+    Tombstone,
+}
+
+/// On-disk record layout shared by SSTables and the write-ahead log.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Value<K, V> {
+    pub key: K,
+    pub entry: Entry<V>,
+}
+
+impl<K, V> Value<K, V> {
+    pub fn of(key: K, value: V) -> Value<K, V> {
+        Value { key, entry: Entry::Value(value) }
+    }
+
+    pub fn tombstone(key: K) -> Value<K, V> {
+        Value { key, entry: Entry::Tombstone }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::errors::Errors;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::LN_2;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension for the sidecar file a `BloomFilter` is persisted under,
+/// alongside its SSTable.
+pub const BLOOM_FILTER_EXTENSION: &str = "filter";
+
+/// Fixed-size bitset bloom filter, one per SSTable. Sized from the
+/// number of keys a table holds and a target false-positive rate; every
+/// key is hashed with `k` independent-enough hashes derived from two
+/// base hashes (`h_i = h1 + i*h2 mod m`) rather than running a real hash
+/// function `k` times.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    m: usize,
+    k: usize,
+}
+
+impl BloomFilter {
+    pub fn with_capacity(key_count: usize, false_positive_rate: f64) -> BloomFilter {
+        let n = (key_count.max(1)) as f64;
+        let m = ((-n * false_positive_rate.ln()) / LN_2.powi(2)).ceil().max(8.0) as usize;
+        let k = (((m as f64) / n) * LN_2).round().max(1.0) as usize;
+        BloomFilter { bits: vec![0u8; m.div_ceil(8)], m, k }
+    }
+
+    pub fn insert<K: Hash>(&mut self, key: &K) {
+        for index in self.bit_indices(key) {
+            self.bits[index / 8] |= 1 << (index % 8);
+        }
+    }
+
+    /// `false` is a guarantee the key is absent; `true` means it *might*
+    /// be present (and the caller must still check the SSTable itself).
+    pub fn may_contain<K: Hash>(&self, key: &K) -> bool {
+        self.bit_indices(key).into_iter().all(|index| self.bits[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    // Returned as an owned `Vec` rather than `impl Iterator<Item = _> + '_`
+    // so callers that mutate `self.bits` while consuming the indices (like
+    // `insert`) aren't left holding an immutable borrow of `self` for the
+    // iterator's lifetime.
+    fn bit_indices<K: Hash>(&self, key: &K) -> Vec<usize> {
+        let (h1, h2) = Self::hash_pair(key);
+        let m = self.m as u64;
+        (0..self.k).map(|i| (h1.wrapping_add((i as u64).wrapping_mul(h2)) % m) as usize).collect()
+    }
+
+    fn hash_pair<K: Hash>(key: &K) -> (u64, u64) {
+        let mut first = DefaultHasher::new();
+        key.hash(&mut first);
+        let h1 = first.finish();
+
+        // derive the second base hash from the first so the two stay
+        // independent enough without a second hash function.
+        let mut second = DefaultHasher::new();
+        h1.hash(&mut second);
+        key.hash(&mut second);
+        let h2 = second.finish();
+
+        (h1, h2)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Errors> {
+        let bytes = bincode::serialize(self).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        let mut file = File::create(path).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        file.write_all(&bytes).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<BloomFilter, Errors> {
+        let mut file = File::open(path).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+        bincode::deserialize(&bytes).map_err(|_| Errors::SSTABLE_READ_FAILED)
+    }
+}
+
+/// Path the filter sidecar for `sstable_path` lives at.
+pub fn filter_path_for(sstable_path: &Path) -> PathBuf {
+    sstable_path.with_extension(BLOOM_FILTER_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_reports_an_inserted_key_as_absent() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("key-{i}")).collect();
+        let mut filter = BloomFilter::with_capacity(keys.len(), 0.01);
+        for key in &keys {
+            filter.insert(key);
+        }
+        for key in &keys {
+            assert!(filter.may_contain(key), "false negative for {key}");
+        }
+    }
+}
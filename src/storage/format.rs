@@ -0,0 +1,40 @@
+use crate::errors::Errors;
+use std::io::{Read, Write};
+
+/// Bytes every SSTable starts with, so a reader can tell an SSTable
+/// written by this crate apart from an arbitrary/corrupt file before it
+/// ever tries to `bincode::deserialize` a record out of it.
+pub const MAGIC: [u8; 4] = *b"DHMA";
+
+/// Current on-disk SSTable format version. Bump this whenever the
+/// `bincode` layout of `Value<K, V>`, block framing, or anything else a
+/// reader assumes about the file changes; `Persistence::upgrade` is what
+/// carries old data directories forward across the bump.
+pub const CURRENT_VERSION: u16 = 1;
+
+/// Size of the header written by `write_header`, in bytes. Readers start
+/// their record cursor here.
+pub const HEADER_LEN: usize = MAGIC.len() + 2;
+
+pub fn write_header<W: Write>(writer: &mut W) -> Result<(), Errors> {
+    writer.write_all(&MAGIC).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+    writer
+        .write_all(&CURRENT_VERSION.to_le_bytes())
+        .map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+    Ok(())
+}
+
+/// Validates the magic bytes and returns the format version found,
+/// without assuming it matches `CURRENT_VERSION` -- callers decide
+/// whether an older version needs `Persistence::upgrade` or a newer one
+/// is simply unsupported by this build.
+pub fn read_header<R: Read>(reader: &mut R) -> Result<u16, Errors> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+    if magic != MAGIC {
+        return Err(Errors::SSTABLE_READ_FAILED);
+    }
+    let mut version_bytes = [0u8; 2];
+    reader.read_exact(&mut version_bytes).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+    Ok(u16::from_le_bytes(version_bytes))
+}
@@ -0,0 +1,6 @@
+pub mod block;
+pub mod bloom_filter;
+pub mod format;
+pub mod sorted_string_table_reader;
+pub mod sorted_string_table_writer;
+pub mod write_ahead_log;
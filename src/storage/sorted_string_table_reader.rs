@@ -0,0 +1,260 @@
+use crate::errors::Errors;
+use crate::storage::format;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Extension used for SSTable files on disk.
+pub const SSTABLE_EXTENSION: &str = "sst";
+
+/// Width every SSTable's numeric id is zero-padded to in its file name
+/// (`format!("{:0width$}.{}", id, SSTABLE_EXTENSION)`, `width =
+/// TABLE_ID_WIDTH`). Wide enough for any `usize`, so two ids always
+/// compare the same way as `PathBuf` sorts their file names as they do
+/// as plain numbers -- without it, `"10.sst"` sorts before `"2.sst"`,
+/// which breaks every place (`TableIndex::newest_to_oldest`,
+/// `SizeTieredPicker`) that relies on path order meaning creation order.
+pub const TABLE_ID_WIDTH: usize = 20;
+
+/// A single record read back from an SSTable, along with the byte offset
+/// it was read from (used to populate the sparse index). Borrows from
+/// the `SSTableReader` that produced it rather than owning its bytes --
+/// on the `Mmap` backend `data` points directly into the mapped file, so
+/// reading a record never allocates.
+#[derive(Clone, Copy, Debug)]
+pub struct SSTableValue<'a> {
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+/// The current record's position and length. Kept separate from the
+/// bytes themselves so `read()` can hand out a borrow tied to `&self`
+/// instead of `current` owning a copy -- the `Buffered` backend has
+/// nowhere else to borrow from, so `scratch` holds its bytes instead.
+#[derive(Clone, Copy)]
+struct CurrentRecord {
+    offset: usize,
+    len: usize,
+}
+
+/// Backing store a reader serves records from. `Mmap` maps the whole
+/// file once and serves every subsequent read from that mapping instead
+/// of issuing a syscall per access -- worthwhile when many lookups land
+/// on the same table. `Buffered` is the original file-backed path, used
+/// when mmap is disabled or the mapping itself fails.
+enum Backend {
+    Buffered(BufReader<File>),
+    Mmap(Mmap),
+}
+
+/// Sequential cursor over an SSTable's length-prefixed records.
+///
+/// `has_next`/`read`/`next` form the standard cursor protocol used
+/// everywhere in this crate: `read()` returns the record the cursor is
+/// currently positioned on without consuming it, `next()` advances past
+/// it, and `has_next()` reports whether there is a record to read.
+pub struct SSTableReader {
+    path: PathBuf,
+    backend: Backend,
+    len: usize,
+    cursor: usize,
+    current: Option<CurrentRecord>,
+    /// Owns the current record's bytes on the `Buffered` backend; unused
+    /// on `Mmap`, where `read()` borrows from the mapping instead.
+    scratch: Vec<u8>,
+}
+
+impl SSTableReader {
+    pub fn from(
+        path: &Path,
+        block_size_in_bytes: usize,
+        use_mmap: bool,
+    ) -> Result<SSTableReader, Errors> {
+        let version = Self::peek_version(path)?;
+        if version > format::CURRENT_VERSION {
+            return Err(Errors::UNSUPPORTED_SSTABLE_VERSION);
+        }
+        Self::open(path, block_size_in_bytes, use_mmap, format::HEADER_LEN)
+    }
+
+    /// Opens `path` as if it predates the versioned header -- skipping
+    /// the header check and starting the record cursor at byte 0. Used
+    /// only by `Persistence::upgrade` to read a table written before
+    /// this format existed, so it can be rewritten with a proper one.
+    pub(crate) fn from_legacy(
+        path: &Path,
+        block_size_in_bytes: usize,
+        use_mmap: bool,
+    ) -> Result<SSTableReader, Errors> {
+        Self::open(path, block_size_in_bytes, use_mmap, 0)
+    }
+
+    /// Returns the format version `path` declares in its header, or
+    /// `None` if it doesn't start with the expected magic bytes at all
+    /// -- i.e. it was written before this format existed.
+    pub(crate) fn table_version(path: &Path) -> Option<u16> {
+        let mut file = File::open(path).ok()?;
+        format::read_header(&mut file).ok()
+    }
+
+    fn peek_version(path: &Path) -> Result<u16, Errors> {
+        let mut file = File::open(path).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+        format::read_header(&mut file)
+    }
+
+    fn open(
+        path: &Path,
+        block_size_in_bytes: usize,
+        use_mmap: bool,
+        start_offset: usize,
+    ) -> Result<SSTableReader, Errors> {
+        let file = File::open(path).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+        let len = file.metadata().map_err(|_| Errors::SSTABLE_READ_FAILED)?.len() as usize;
+        let backend = if use_mmap {
+            // SAFETY: the file is not expected to be truncated or
+            // modified by another process while mapped; SSTables are
+            // immutable once written and only ever removed after
+            // compaction. The one in-place rewrite is
+            // `Persistence::upgrade`, which runs at startup before a
+            // table is served and never reads the old mapping after it.
+            match unsafe { Mmap::map(&file) } {
+                Ok(mmap) => Backend::Mmap(mmap),
+                Err(_) => Backend::Buffered(Self::buffered(file, block_size_in_bytes)),
+            }
+        } else {
+            Backend::Buffered(Self::buffered(file, block_size_in_bytes))
+        };
+        let mut reader = SSTableReader {
+            path: path.to_path_buf(),
+            backend,
+            len,
+            cursor: start_offset,
+            current: None,
+            scratch: Vec::new(),
+        };
+        reader.load_current()?;
+        Ok(reader)
+    }
+
+    fn buffered(file: File, block_size_in_bytes: usize) -> BufReader<File> {
+        BufReader::with_capacity(block_size_in_bytes, file)
+    }
+
+    /// Returns every `*.sst` file in `dir`, sorted oldest-first.
+    pub fn get_valid_table_paths(dir: &Path) -> Result<Vec<PathBuf>, Errors> {
+        let read_dir = std::fs::read_dir(dir).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+        let mut paths: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == SSTABLE_EXTENSION))
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// An id guaranteed to not collide with any `*.sst` currently in
+    /// `dir`: one past the largest id found there, or `0` if `dir` holds
+    /// no tables yet. Deriving the next id from a *count* of tables
+    /// (e.g. how many paths a listing returned, or how many a
+    /// compaction job covers) is not safe -- a count is smaller than
+    /// the id of any table outside of what was counted, so writing to
+    /// `{count}.sst` can truncate a live table that just wasn't part of
+    /// that count.
+    pub fn next_table_id(dir: &Path) -> Result<usize, Errors> {
+        let max_id = Self::get_valid_table_paths(dir)?.iter().filter_map(|path| Self::table_id(path).ok()).max();
+        Ok(max_id.map_or(0, |id| id + 1))
+    }
+
+    /// Parses the numeric id `write_sstable` embedded in `path`'s file
+    /// name.
+    pub(crate) fn table_id(path: &Path) -> Result<usize, Errors> {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<usize>().ok())
+            .ok_or(Errors::SSTABLE_READ_FAILED)
+    }
+
+    /// Repositions the cursor at `offset` and loads the record found
+    /// there. `offset` is expected to be the start of a record, as
+    /// produced by a previously read `SSTableValue.offset`.
+    pub fn seek_closest(&mut self, offset: usize) -> Result<(), Errors> {
+        if offset > self.len {
+            return Err(Errors::SSTABLE_READ_FAILED);
+        }
+        self.cursor = offset;
+        self.load_current()
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn read(&self) -> SSTableValue<'_> {
+        let current = self.current.expect("read() called without checking has_next() first");
+        let data = match &self.backend {
+            // zero-copy: borrowed straight out of the mapping, no
+            // allocation per read.
+            Backend::Mmap(mmap) => &mmap[current.offset + 4..current.offset + 4 + current.len],
+            Backend::Buffered(_) => &self.scratch,
+        };
+        SSTableValue { offset: current.offset, data }
+    }
+
+    pub fn next(&mut self) {
+        if let Some(current) = self.current {
+            self.cursor = current.offset + 4 + current.len;
+        }
+        // A failure here means the record at the cursor runs past the end
+        // of the file; treat that the same as reaching the end.
+        if self.load_current().is_err() {
+            self.current = None;
+        }
+    }
+
+    fn load_current(&mut self) -> Result<(), Errors> {
+        if self.cursor >= self.len {
+            self.current = None;
+            return Ok(());
+        }
+        match &mut self.backend {
+            Backend::Mmap(mmap) => {
+                let record = Self::record_at(mmap, self.cursor)?;
+                self.current = Some(record);
+                Ok(())
+            }
+            Backend::Buffered(reader) => {
+                reader
+                    .seek(SeekFrom::Start(self.cursor as u64))
+                    .map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+                let mut len_bytes = [0u8; 4];
+                reader.read_exact(&mut len_bytes).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+                let record_len = u32::from_le_bytes(len_bytes) as usize;
+                self.scratch.resize(record_len, 0);
+                reader.read_exact(&mut self.scratch).map_err(|_| Errors::SSTABLE_READ_FAILED)?;
+                self.current = Some(CurrentRecord { offset: self.cursor, len: record_len });
+                Ok(())
+            }
+        }
+    }
+
+    /// Validates that a length-prefixed record sits at `offset` within
+    /// `mmap`, without copying its bytes out.
+    fn record_at(mmap: &Mmap, offset: usize) -> Result<CurrentRecord, Errors> {
+        let header_end = offset + 4;
+        if header_end > mmap.len() {
+            return Err(Errors::SSTABLE_READ_FAILED);
+        }
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&mmap[offset..header_end]);
+        let record_len = u32::from_le_bytes(len_bytes) as usize;
+        if header_end + record_len > mmap.len() {
+            return Err(Errors::SSTABLE_READ_FAILED);
+        }
+        Ok(CurrentRecord { offset, len: record_len })
+    }
+}
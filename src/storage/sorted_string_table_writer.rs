@@ -0,0 +1,94 @@
+use crate::errors::Errors;
+use crate::options::DharmaOpts;
+use crate::storage::block::{Entry, Value};
+use crate::storage::bloom_filter::{filter_path_for, BloomFilter};
+use crate::storage::format;
+use crate::storage::sorted_string_table_reader::{SSTABLE_EXTENSION, TABLE_ID_WIDTH};
+use crate::traits::{ResourceKey, ResourceValue};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes `entries` (expected to already be in ascending key order) out
+/// as a new SSTable named `{index}.sst` (zero-padded to `TABLE_ID_WIDTH`
+/// so path order keeps matching id order) inside `options.path`, using
+/// the same length-prefixed record framing `SSTableReader` reads back.
+/// Tombstones are written out just like live values -- it is up to
+/// compaction to decide when they can finally be dropped.
+///
+/// `index` must be an id not already in use by a live table in
+/// `options.path` -- callers get one from
+/// `SSTableReader::next_table_id`, never from a count of tables, since a
+/// count can collide with a table that count didn't include. As a last
+/// line of defense this refuses to clobber an existing file rather than
+/// silently truncating it.
+///
+/// Alongside the table, a bloom filter covering every key in `entries`
+/// (live or tombstoned) is built and saved as a `.filter` sidecar, and
+/// handed back so the caller can keep it in memory without re-reading it
+/// from disk.
+pub fn write_sstable<K, V>(
+    options: &DharmaOpts,
+    entries: &[(K, Entry<V>)],
+    index: usize,
+) -> Result<(PathBuf, BloomFilter), Errors>
+where
+    K: ResourceKey,
+    V: ResourceValue,
+{
+    let path = options.path.join(table_file_name(index));
+    if path.exists() {
+        return Err(Errors::SSTABLE_CREATION_FAILED);
+    }
+    let mut filter =
+        BloomFilter::with_capacity(entries.len(), options.bloom_filter_false_positive_rate);
+    write_records(&path, entries, |key| filter.insert(key))?;
+    filter.save(&filter_path_for(&path))?;
+    Ok((path, filter))
+}
+
+fn table_file_name(index: usize) -> String {
+    format!("{:0width$}.{}", index, SSTABLE_EXTENSION, width = TABLE_ID_WIDTH)
+}
+
+/// Rewrites `path` in place with a current-version header followed by
+/// `entries`, discarding whatever framing it had before. Used only by
+/// `Persistence::upgrade` to carry an SSTable written by an older
+/// version of this crate forward -- the keys it covers don't change, so
+/// the existing bloom filter sidecar stays valid and is left untouched.
+pub(crate) fn rewrite_with_header<K, V>(
+    path: &Path,
+    entries: &[(K, Entry<V>)],
+) -> Result<(), Errors>
+where
+    K: ResourceKey,
+    V: ResourceValue,
+{
+    write_records(path, entries, |_| {})
+}
+
+fn write_records<K, V>(
+    path: &Path,
+    entries: &[(K, Entry<V>)],
+    mut on_key: impl FnMut(&K),
+) -> Result<(), Errors>
+where
+    K: ResourceKey,
+    V: ResourceValue,
+{
+    let file = File::create(path).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+    let mut writer = BufWriter::new(file);
+    format::write_header(&mut writer)?;
+    for (key, entry) in entries {
+        let record = Value { key: key.clone(), entry: entry.clone() };
+        let bytes = bincode::serialize(&record).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        writer
+            .write_all(&(bytes.len() as u32).to_le_bytes())
+            .map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        writer.write_all(&bytes).map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+        on_key(key);
+    }
+    writer.flush().map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+    writer.get_ref().sync_all().map_err(|_| Errors::SSTABLE_CREATION_FAILED)?;
+    Ok(())
+}
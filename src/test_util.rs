@@ -0,0 +1,18 @@
+//! Shared helpers for this crate's tests only.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Creates (and returns the path to) a fresh, empty directory under the
+/// system temp dir, unique to this test run -- so tests that touch the
+/// filesystem never collide with each other or with a prior run.
+pub(crate) fn temp_dir(label: &str) -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("dharma-db-test-{label}-{nanos}-{count}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,23 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Bound satisfied by any type that can be used as a key: it must be
+/// orderable (SSTables and the sparse index rely on key ordering),
+/// hashable (used by the bloom filter and in-memory maps) and
+/// (de)serializable so it can be written to and read back from disk.
+pub trait ResourceKey:
+    Clone + Debug + Eq + Ord + Hash + Send + Sync + Serialize + DeserializeOwned + 'static
+{
+}
+
+impl<T> ResourceKey for T where
+    T: Clone + Debug + Eq + Ord + Hash + Send + Sync + Serialize + DeserializeOwned + 'static
+{
+}
+
+/// Bound satisfied by any type that can be used as a value.
+pub trait ResourceValue: Clone + Debug + Send + Sync + Serialize + DeserializeOwned + 'static {}
+
+impl<T> ResourceValue for T where T: Clone + Debug + Send + Sync + Serialize + DeserializeOwned + 'static {}